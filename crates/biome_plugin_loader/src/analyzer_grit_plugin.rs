@@ -1,85 +1,367 @@
 use std::{
     borrow::Cow,
-    fmt::Debug,
+    cell::RefCell,
+    ffi::OsStr,
+    fmt::{self, Debug},
     path::{Path, PathBuf},
     rc::Rc,
+    str::FromStr,
+    sync::mpsc::{self, Receiver},
 };
 
-use biome_analyze::RuleDiagnostic;
+use biome_analyze::{CodeSuggestionAdvice, RuleDiagnostic};
 use biome_console::markup;
-use biome_diagnostics::category;
+use biome_diagnostics::{category, Applicability, Category, Severity};
 use biome_fs::FileSystem;
 use biome_grit_patterns::{
-    compile_pattern, BuiltInFunction, GritBinding, GritExecContext, GritPattern, GritQuery,
-    GritQueryContext, GritQueryState, GritResolvedPattern, GritTargetFile, GritTargetLanguage,
-    JsTargetLanguage,
+    compile_pattern, BuiltInFunction, CssTargetLanguage, GraphqlTargetLanguage, GritBinding,
+    GritExecContext, GritPattern, GritQuery, GritQueryContext, GritQueryState,
+    GritResolvedPattern, GritTargetFile, GritTargetLanguage, JsTargetLanguage, JsonTargetLanguage,
 };
 use biome_parser::AnyParse;
 use biome_rowan::TextRange;
 use grit_pattern_matcher::{binding::Binding, pattern::ResolvedPattern};
 use grit_util::{error::GritPatternError, AnalysisLogs};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
 use crate::{AnalyzerPlugin, PluginDiagnostic};
 
 /// Definition of an analyzer plugin.
-#[derive(Clone, Debug)]
+///
+/// Cloning shares the same underlying query and filesystem watch, so every
+/// clone observes the same hot-reloaded state.
+#[derive(Clone)]
 pub struct AnalyzerGritPlugin {
-    grit_query: Rc<GritQuery>,
+    inner: Rc<RefCell<AnalyzerGritPluginState>>,
+}
+
+struct AnalyzerGritPluginState {
+    source: String,
+    path: PathBuf,
+    /// The language declared by a leading `language <name>;` statement in
+    /// the plugin's own source, if any. When present, it pins the plugin to
+    /// that one language; when absent, the language is instead inferred
+    /// per-file from the document being analyzed, so one plugin set can run
+    /// across a mixed-language project.
+    declared_language: Option<GritTargetLanguage>,
+    /// Compiled queries per target language: the one compiled at load time,
+    /// plus one compiled lazily for each other language the plugin has
+    /// actually been asked to match against.
+    queries: Vec<(GritTargetLanguage, Rc<GritQuery>)>,
+    changes: Receiver<()>,
+    // Kept alive only so the watch stays registered; never read.
+    _watcher: RecommendedWatcher,
+}
+
+impl Debug for AnalyzerGritPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.borrow();
+        f.debug_struct("AnalyzerGritPlugin")
+            .field("path", &inner.path)
+            .field("declared_language", &inner.declared_language)
+            .finish()
+    }
 }
 
 impl AnalyzerGritPlugin {
     pub fn load(fs: &dyn FileSystem, path: &Path) -> Result<Self, PluginDiagnostic> {
         let source = fs.read_file_from_path(path)?;
-        let query = compile_pattern(
-            &source,
-            Some(path),
-            // TODO: Target language should be determined dynamically.
-            GritTargetLanguage::JsTargetLanguage(JsTargetLanguage),
-            vec![BuiltInFunction::new(
-                "register_diagnostic",
-                &[
-                    "span",
-                    "message",
-                    "fixer_description",
-                    "category",
-                    "applicability",
-                ],
-                Box::new(register_diagnostic),
-            )
-            .as_predicate()],
-        )?;
+        let declared_language = language_from_source(&source);
+        // Compile eagerly once, against the declared language (or a default
+        // if the plugin doesn't pin one), so a syntax error in the plugin
+        // surfaces immediately at load time rather than on first use.
+        let initial_language = declared_language
+            .clone()
+            .unwrap_or(GritTargetLanguage::JsTargetLanguage(JsTargetLanguage));
+        let query = compile_for_language(&source, path, initial_language.clone())?;
+
+        let watch_dir = path.parent().unwrap_or(path).to_path_buf();
+        let file_name = path.file_name().map(ToOwned::to_owned);
+        let (sender, changes) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if matches!(&event, Ok(event) if event_concerns_file(event, file_name.as_deref())) {
+                // A failed send just means the plugin was already dropped.
+                let _ = sender.send(());
+            }
+        })
+        .map_err(|error| GritPatternError::new(error.to_string()))?;
+        // Watch the parent directory rather than the file itself: editors
+        // commonly save by writing a temp file and renaming it over the
+        // original, which replaces the inode the watch was registered
+        // against and would otherwise silently kill the watch after the
+        // very first such save.
+        watcher
+            .watch(&watch_dir, RecursiveMode::NonRecursive)
+            .map_err(|error| GritPatternError::new(error.to_string()))?;
 
         Ok(Self {
-            grit_query: Rc::new(query),
+            inner: Rc::new(RefCell::new(AnalyzerGritPluginState {
+                source,
+                path: path.to_path_buf(),
+                declared_language,
+                queries: vec![(initial_language, Rc::new(query))],
+                changes,
+                _watcher: watcher,
+            })),
         })
     }
+
+    /// Returns the compiled query for `language`, compiling and caching it
+    /// on first use.
+    fn query_for_language(&self, language: &GritTargetLanguage) -> Result<Rc<GritQuery>, PluginDiagnostic> {
+        let (source, path) = {
+            let inner = self.inner.borrow();
+            if let Some((_, query)) = inner.queries.iter().find(|(cached, _)| cached == language) {
+                return Ok(Rc::clone(query));
+            }
+            (inner.source.clone(), inner.path.clone())
+        };
+
+        let query = Rc::new(compile_for_language(&source, &path, language.clone())?);
+
+        let mut inner = self.inner.borrow_mut();
+        inner.queries.push((language.clone(), Rc::clone(&query)));
+        Ok(query)
+    }
+
+    /// Recompiles the plugin if the watcher reported a change to its source
+    /// since the last evaluation. Reads straight from disk rather than
+    /// through the virtual `FileSystem`, since the watch itself only ever
+    /// fires for real filesystem paths.
+    ///
+    /// A plugin without a `language` declaration may by now have compiled
+    /// queries cached for several languages (one per language of file it's
+    /// actually been evaluated against), not just the one it was first
+    /// loaded with. Recompile every language currently in the cache rather
+    /// than just the first one, so editing the plugin in a way that's valid
+    /// for JS but breaks a CSS-specific snippet doesn't silently drop the
+    /// working CSS query. Each language is kept on its own previously
+    /// working query if its recompile fails, so a transient syntax error
+    /// doesn't disable linting; failures are reported as diagnostics instead.
+    fn reload_if_changed(&self) -> Vec<RuleDiagnostic> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.changes.try_recv().is_err() {
+            return Vec::new();
+        }
+        // Multiple change events may have queued up (e.g. editors that save
+        // in several steps); only the latest contents matter.
+        while inner.changes.try_recv().is_ok() {}
+
+        let source = match std::fs::read_to_string(&inner.path) {
+            Ok(source) => source,
+            Err(error) => {
+                return vec![reload_failure_diagnostic(&inner.path, &error.to_string())];
+            }
+        };
+
+        let declared_language = language_from_source(&source);
+        let languages_to_recompile: Vec<GritTargetLanguage> = if let Some(declared) =
+            &declared_language
+        {
+            vec![declared.clone()]
+        } else {
+            inner
+                .queries
+                .iter()
+                .map(|(language, _)| language.clone())
+                .collect()
+        };
+
+        let mut diagnostics = Vec::new();
+        let mut queries = Vec::with_capacity(languages_to_recompile.len());
+        for language in languages_to_recompile {
+            match compile_for_language(&source, &inner.path, language.clone()) {
+                Ok(query) => queries.push((language, Rc::new(query))),
+                Err(diagnostic) => {
+                    diagnostics.push(reload_failure_diagnostic(
+                        &inner.path,
+                        &diagnostic.to_string(),
+                    ));
+                    if let Some(previous) = inner
+                        .queries
+                        .iter()
+                        .find(|(cached, _)| *cached == language)
+                    {
+                        queries.push(previous.clone());
+                    }
+                }
+            }
+        }
+
+        inner.source = source;
+        inner.declared_language = declared_language;
+        inner.queries = queries;
+        diagnostics
+    }
+}
+
+fn reload_failure_diagnostic(path: &Path, error: &str) -> RuleDiagnostic {
+    RuleDiagnostic::new(
+        category!("plugin"),
+        None::<TextRange>,
+        markup!(<Error>"failed to reload plugin "{path.to_string_lossy().as_ref()}": "{error}</Error>),
+    )
+}
+
+fn compile_for_language(
+    source: &str,
+    path: &Path,
+    language: GritTargetLanguage,
+) -> Result<GritQuery, PluginDiagnostic> {
+    let query = compile_pattern(
+        source,
+        Some(path),
+        language,
+        vec![BuiltInFunction::new(
+            "register_diagnostic",
+            &[
+                "span",
+                "message",
+                "fixer_description",
+                "category",
+                "applicability",
+                "severity",
+                "labels",
+            ],
+            Box::new(register_diagnostic),
+        )
+        .as_predicate()],
+    )
+    .map_err(annotate_unknown_builtin)?;
+
+    Ok(query)
+}
+
+/// Whether a filesystem event observed on the watched parent directory
+/// concerns `file_name`. Treats create/modify/remove alike, since editors
+/// commonly replace a file by renaming a temp file over it, which shows up
+/// as a remove-then-create rather than a modify.
+fn event_concerns_file(event: &notify::Event, file_name: Option<&OsStr>) -> bool {
+    let Some(file_name) = file_name else {
+        return false;
+    };
+    if !matches!(
+        event.kind,
+        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+    ) {
+        return false;
+    }
+    event
+        .paths
+        .iter()
+        .any(|path| path.file_name() == Some(file_name))
+}
+
+/// Looks for a leading `language <name>;` declaration in a `.grit` source,
+/// as documented for Grit standalone pattern files, and maps it to the
+/// corresponding [`GritTargetLanguage`].
+fn language_from_source(source: &str) -> Option<GritTargetLanguage> {
+    let declaration = source
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("//"))
+        .filter(|line| line.starts_with("language"))?;
+
+    let name = declaration
+        .trim_start_matches("language")
+        .trim()
+        .trim_end_matches(';')
+        .trim();
+
+    language_from_name(name)
+}
+
+/// Infers the target language from the extension of the analyzed document's
+/// path. Unrecognized extensions, including `.grit`, yield `None`.
+fn language_from_extension(path: &Path) -> Option<GritTargetLanguage> {
+    language_from_name(path.extension()?.to_str()?)
+}
+
+fn language_from_name(name: &str) -> Option<GritTargetLanguage> {
+    match name.to_ascii_lowercase().as_str() {
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => {
+            Some(GritTargetLanguage::JsTargetLanguage(JsTargetLanguage))
+        }
+        "css" => Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage)),
+        "json" | "jsonc" => Some(GritTargetLanguage::JsonTargetLanguage(JsonTargetLanguage)),
+        "graphql" | "gql" => Some(GritTargetLanguage::GraphqlTargetLanguage(
+            GraphqlTargetLanguage,
+        )),
+        _ => None,
+    }
+}
+
+/// Determines the language a plugin should evaluate a file against, or
+/// `None` if the plugin has no business looking at this file at all.
+///
+/// A plugin that pins itself to one language via a `language` declaration
+/// only ever matches files of that language, so it skips files whose
+/// language isn't recognized (`file_language` is `None`) too, exactly like
+/// an undeclared plugin skips them below. A plugin without a declaration instead
+/// infers its target language from the file actually being analyzed, so it
+/// can run against any language Biome parses.
+fn target_language_for_file(
+    declared_language: Option<GritTargetLanguage>,
+    file_language: Option<GritTargetLanguage>,
+) -> Option<GritTargetLanguage> {
+    match declared_language {
+        Some(declared) => match file_language {
+            Some(language) if language == declared => Some(declared),
+            _ => None,
+        },
+        None => file_language,
+    }
 }
 
 impl AnalyzerPlugin for AnalyzerGritPlugin {
     fn evaluate(&self, root: AnyParse, path: PathBuf) -> Vec<RuleDiagnostic> {
-        let name: &str = self.grit_query.name.as_deref().unwrap_or("anonymous");
+        let mut diagnostics = self.reload_if_changed();
+
+        let declared_language = self.inner.borrow().declared_language.clone();
+        let file_language = language_from_extension(&path);
 
+        let target_language = match target_language_for_file(declared_language, file_language) {
+            Some(language) => language,
+            None => return diagnostics,
+        };
+
+        let grit_query = match self.query_for_language(&target_language) {
+            Ok(query) => query,
+            Err(diagnostic) => {
+                diagnostics.push(RuleDiagnostic::new(
+                    category!("plugin"),
+                    None::<TextRange>,
+                    markup!(<Error>{diagnostic.to_string()}</Error>),
+                ));
+                return diagnostics;
+            }
+        };
+
+        let name: &str = grit_query.name.as_deref().unwrap_or("anonymous");
         let file = GritTargetFile { parse: root, path };
-        match self.grit_query.execute(file) {
-            Ok(result) => result
-                .logs
-                .iter()
-                .map(|log| {
-                    RuleDiagnostic::new(
-                        category!("plugin"),
-                        log.range.map(from_grit_range),
-                        markup!(<Emphasis>{name}</Emphasis>" logged: "<Info>{log.message}</Info>),
-                    )
-                    .verbose()
-                })
-                .chain(result.diagnostics)
-                .collect(),
-            Err(error) => vec![RuleDiagnostic::new(
+        match grit_query.execute(file) {
+            Ok(result) => diagnostics.extend(
+                result
+                    .logs
+                    .iter()
+                    .map(|log| {
+                        RuleDiagnostic::new(
+                            category!("plugin"),
+                            log.range.map(from_grit_range),
+                            markup!(<Emphasis>{name}</Emphasis>" logged: "<Info>{log.message}</Info>),
+                        )
+                        .verbose()
+                    })
+                    .chain(result.diagnostics),
+            ),
+            Err(error) => diagnostics.push(RuleDiagnostic::new(
                 category!("plugin"),
                 None::<TextRange>,
                 markup!(<Emphasis>{name}</Emphasis>" errored: "<Error>{error.to_string()}</Error>),
-            )],
+            )),
         }
+
+        diagnostics
     }
 }
 
@@ -95,21 +377,98 @@ fn register_diagnostic<'a>(
 ) -> Result<GritResolvedPattern<'a>, GritPatternError> {
     let args = GritResolvedPattern::from_patterns(args, state, context, logs)?;
 
-    let (span_node, message, _fixer_description, _category, _applicability) = match args.as_slice() {
-        [Some(span), Some(message), None, None, None] => (span, message, None, None, None),
-        [Some(span), Some(message), Some(fixer_description), Some(category), Some(applicability)] => (span, message, Some(fixer_description), Some(category), Some(applicability)),
-        // TODO: Do we want to make `category` and `applicability` optional, even for rules with a fixer?
-        _ => return Err(GritPatternError::new(
-            "register_diagnostic() takes 2 or 5 arguments: span and message, and optional fixer_description, category and applicability",
-        )),
-    };
+    // `register_diagnostic` is registered with a fixed 7-slot signature, so
+    // `from_patterns` always pads `args` out to that arity; missing trailing
+    // arguments show up as `None` entries, not a shorter vec.
+    let span_node = args[0]
+        .as_ref()
+        .ok_or_else(|| GritPatternError::new("register_diagnostic() requires a span"))?;
+    let message = args[1]
+        .as_ref()
+        .ok_or_else(|| GritPatternError::new("register_diagnostic() requires a message"))?;
+    let fixer_description = args.get(2).and_then(Option::as_ref);
+    let category = args.get(3).and_then(Option::as_ref);
+    let applicability = args.get(4).and_then(Option::as_ref);
+    let severity = args.get(5).and_then(Option::as_ref);
+    let labels = args.get(6).and_then(Option::as_ref);
+
+    // TODO: Do we want to make `category` and `applicability` optional, even for rules with a fixer?
+    if fixer_description.is_some() != category.is_some() || category.is_some() != applicability.is_some() {
+        return Err(GritPatternError::new(
+            "register_diagnostic() fixer_description, category and applicability must be supplied together",
+        ));
+    }
 
     let span = span_node
         .get_last_binding()
         .and_then(GritBinding::as_node)
         .map(|node| node.text_trimmed_range());
 
-    let message = match message {
+    let message = resolved_pattern_text(message, state, context);
+    let message = message.as_deref().unwrap_or("(no message)");
+
+    let category = match category {
+        Some(category) => {
+            let name = resolved_pattern_text(category, state, context).ok_or_else(|| {
+                GritPatternError::new("register_diagnostic() category must be a string")
+            })?;
+            resolve_category(&name)?
+        }
+        None => category!("plugin"),
+    };
+
+    let mut diagnostic = RuleDiagnostic::new(category, span, message);
+
+    if let Some(severity) = severity {
+        let severity = resolved_pattern_text(severity, state, context)
+            .ok_or_else(|| {
+                GritPatternError::new("register_diagnostic() severity must be a string")
+            })
+            .and_then(|name| parse_severity(&name))?;
+        diagnostic = diagnostic.severity(severity);
+    }
+
+    if let (Some(fixer_description), Some(applicability)) = (fixer_description, applicability) {
+        let applicability = resolved_pattern_text(applicability, state, context)
+            .ok_or_else(|| {
+                GritPatternError::new("register_diagnostic() applicability must be a string")
+            })
+            .and_then(|name| parse_applicability(&name))?;
+        let fixer_description = resolved_pattern_text(fixer_description, state, context)
+            .ok_or_else(|| {
+                GritPatternError::new("register_diagnostic() fixer_description must be a string")
+            })?;
+        let suggestion_span = span.ok_or_else(|| {
+            GritPatternError::new("register_diagnostic() can only attach a fixer when a span is provided")
+        })?;
+
+        diagnostic = diagnostic.add_code_suggestion(CodeSuggestionAdvice {
+            applicability,
+            msg: markup! { {fixer_description.as_ref()} }.to_owned(),
+            suggestion: fixer_description.into_owned(),
+            span: suggestion_span,
+        });
+    }
+
+    if let Some(labels) = labels {
+        for (label_span, label) in parse_secondary_labels(labels, state, context)? {
+            diagnostic = diagnostic.detail(label_span, label.as_ref());
+        }
+    }
+
+    context.add_diagnostic(diagnostic);
+
+    Ok(span_node.clone())
+}
+
+/// Extracts the textual contents of a resolved Grit pattern, whether it was
+/// bound as a constant, a snippet, or a regular node binding.
+fn resolved_pattern_text<'a>(
+    pattern: &GritResolvedPattern<'a>,
+    state: &GritQueryState<'a, GritQueryContext>,
+    context: &GritExecContext,
+) -> Option<Cow<'a, str>> {
+    match pattern {
         GritResolvedPattern::Constant(constant) => Some(constant.to_string().into()),
         GritResolvedPattern::Snippets(snippets) => snippets
             .iter()
@@ -125,10 +484,449 @@ fn register_diagnostic<'a>(
         resolved_pattern => resolved_pattern
             .get_last_binding()
             .and_then(|binding| binding.text(&context.lang).ok()),
+    }
+}
+
+/// Parses the optional `labels` argument of `register_diagnostic()`, a list
+/// of `{ span, label }` pairs, into secondary spans that get attached as
+/// detail advice on the diagnostic (e.g. "defined here" / "used here").
+fn parse_secondary_labels<'a>(
+    pattern: &GritResolvedPattern<'a>,
+    state: &GritQueryState<'a, GritQueryContext>,
+    context: &GritExecContext,
+) -> Result<Vec<(TextRange, Cow<'a, str>)>, GritPatternError> {
+    let GritResolvedPattern::List(items) = pattern else {
+        return Err(GritPatternError::new(
+            "register_diagnostic() labels must be a list of { span, label } pairs",
+        ));
     };
-    let message = message.as_deref().unwrap_or("(no message)");
 
-    context.add_diagnostic(RuleDiagnostic::new(category!("plugin"), span, message));
+    items
+        .iter()
+        .map(|item| {
+            let GritResolvedPattern::Map(fields) = item else {
+                return Err(GritPatternError::new(
+                    "register_diagnostic() each label must be a { span, label } pair",
+                ));
+            };
 
-    Ok(span_node.clone())
+            let span = fields
+                .get("span")
+                .and_then(Option::as_ref)
+                .and_then(GritResolvedPattern::get_last_binding)
+                .and_then(GritBinding::as_node)
+                .map(|node| node.text_trimmed_range())
+                .ok_or_else(|| {
+                    GritPatternError::new("register_diagnostic() label is missing its span")
+                })?;
+
+            let label = fields
+                .get("label")
+                .and_then(Option::as_ref)
+                .and_then(|label| resolved_pattern_text(label, state, context))
+                .ok_or_else(|| {
+                    GritPatternError::new("register_diagnostic() label is missing its text")
+                })?;
+
+            Ok((span, label))
+        })
+        .collect()
+}
+
+/// Resolves a plugin-supplied category name against Biome's registered rule
+/// categories.
+fn resolve_category(name: &str) -> Result<&'static Category, GritPatternError> {
+    Category::from_str(name).map_err(|_| {
+        GritPatternError::new(match suggest_closest(name, Category::ALL_CATEGORIES) {
+            Some(suggestion) => format!("unknown category \"{name}\"; did you mean \"{suggestion}\"?"),
+            None => format!("unknown category \"{name}\""),
+        })
+    })
+}
+
+/// Names of the built-in predicates this crate registers on top of Grit's
+/// own, used to give plugin authors a "did you mean" hint when a `load()`
+/// fails to resolve a predicate they called.
+const KNOWN_BUILTINS: &[&str] = &["register_diagnostic"];
+
+/// Enriches a `compile_pattern()` failure caused by an unresolved predicate
+/// name with the closest known built-in, if any is close enough to be
+/// useful.
+fn annotate_unknown_builtin(error: GritPatternError) -> GritPatternError {
+    let message = error.to_string();
+    let Some(name) = message.strip_suffix(" is not defined") else {
+        return error;
+    };
+    match suggest_closest(name, KNOWN_BUILTINS) {
+        Some(suggestion) => {
+            GritPatternError::new(format!("{message}; did you mean \"{suggestion}\"?"))
+        }
+        None => error,
+    }
+}
+
+/// Finds the known name closest to `name` by Levenshtein edit distance,
+/// provided it's close enough that the match is likely a typo rather than
+/// a genuinely different identifier.
+fn suggest_closest(name: &str, known: &[&'static str]) -> Option<&'static str> {
+    let threshold = (name.len() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| (*candidate, levenshtein_distance(name, candidate)))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Classic Wagner-Fischer edit distance between two strings.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    let mut current_row = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.iter().enumerate() {
+        current_row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = usize::from(a_char != b_char);
+            current_row[j + 1] = (previous_row[j + 1] + 1)
+                .min(current_row[j] + 1)
+                .min(previous_row[j] + substitution_cost);
+        }
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+
+    previous_row[b.len()]
+}
+
+/// Parses a plugin-supplied applicability string into the enum used by
+/// Biome's code actions.
+fn parse_applicability(name: &str) -> Result<Applicability, GritPatternError> {
+    match name {
+        "always" => Ok(Applicability::Always),
+        "maybe-incorrect" => Ok(Applicability::MaybeIncorrect),
+        other => Err(GritPatternError::new(format!(
+            "invalid applicability \"{other}\"; expected \"always\" or \"maybe-incorrect\""
+        ))),
+    }
+}
+
+/// Parses a plugin-supplied severity string into the enum used by Biome's
+/// diagnostic engine, letting a single plugin surface both hard failures and
+/// advisory notes.
+fn parse_severity(name: &str) -> Result<Severity, GritPatternError> {
+    match name {
+        "error" => Ok(Severity::Error),
+        "warning" => Ok(Severity::Warning),
+        "information" => Ok(Severity::Information),
+        "hint" => Ok(Severity::Hint),
+        other => Err(GritPatternError::new(format!(
+            "invalid severity \"{other}\"; expected one of \"error\", \"warning\", \"information\" or \"hint\""
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_from_source_reads_leading_declaration() {
+        assert_eq!(
+            language_from_source("language css;\n\n`a` => `b`"),
+            Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+        );
+        assert_eq!(
+            language_from_source("// a comment\nlanguage graphql;\n"),
+            Some(GritTargetLanguage::GraphqlTargetLanguage(
+                GraphqlTargetLanguage
+            ))
+        );
+    }
+
+    #[test]
+    fn language_from_source_is_none_without_a_declaration() {
+        assert_eq!(language_from_source("`a` => `b`"), None);
+    }
+
+    #[test]
+    fn target_language_for_file_without_a_declaration_follows_the_file() {
+        assert_eq!(
+            target_language_for_file(
+                None,
+                Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+            ),
+            Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+        );
+        assert_eq!(target_language_for_file(None, None), None);
+    }
+
+    #[test]
+    fn target_language_for_file_with_a_matching_declaration_runs() {
+        assert_eq!(
+            target_language_for_file(
+                Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage)),
+                Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+            ),
+            Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+        );
+    }
+
+    #[test]
+    fn target_language_for_file_skips_a_mismatched_declaration() {
+        assert_eq!(
+            target_language_for_file(
+                Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage)),
+                Some(GritTargetLanguage::JsTargetLanguage(JsTargetLanguage))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn target_language_for_file_skips_a_declaration_against_an_unrecognized_file() {
+        assert_eq!(
+            target_language_for_file(
+                Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage)),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn language_from_extension_maps_known_extensions() {
+        assert_eq!(
+            language_from_extension(Path::new("component.tsx")),
+            Some(GritTargetLanguage::JsTargetLanguage(JsTargetLanguage))
+        );
+        assert_eq!(
+            language_from_extension(Path::new("styles.css")),
+            Some(GritTargetLanguage::CssTargetLanguage(CssTargetLanguage))
+        );
+        assert_eq!(
+            language_from_extension(Path::new("data.jsonc")),
+            Some(GritTargetLanguage::JsonTargetLanguage(JsonTargetLanguage))
+        );
+        assert_eq!(language_from_extension(Path::new("plugin.grit")), None);
+    }
+
+    #[test]
+    fn event_concerns_file_matches_by_name_for_modify_create_and_remove() {
+        let file_name = OsStr::new("my-plugin.grit");
+
+        let modify = notify::Event::new(EventKind::Modify(notify::event::ModifyKind::Data(
+            notify::event::DataChange::Content,
+        )))
+        .add_path(PathBuf::from("/plugins/my-plugin.grit"));
+        assert!(event_concerns_file(&modify, Some(file_name)));
+
+        // A "safe write" save shows up as a remove of the old inode followed
+        // by a create of the new one; both must still trigger a reload.
+        let remove = notify::Event::new(EventKind::Remove(notify::event::RemoveKind::File))
+            .add_path(PathBuf::from("/plugins/my-plugin.grit"));
+        assert!(event_concerns_file(&remove, Some(file_name)));
+
+        let create = notify::Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path(PathBuf::from("/plugins/my-plugin.grit"));
+        assert!(event_concerns_file(&create, Some(file_name)));
+    }
+
+    #[test]
+    fn event_concerns_file_ignores_other_files_in_the_directory() {
+        let file_name = OsStr::new("my-plugin.grit");
+        let event = notify::Event::new(EventKind::Modify(notify::event::ModifyKind::Data(
+            notify::event::DataChange::Content,
+        )))
+        .add_path(PathBuf::from("/plugins/unrelated.grit"));
+
+        assert!(!event_concerns_file(&event, Some(file_name)));
+    }
+
+    #[test]
+    fn parse_applicability_accepts_known_values() {
+        assert!(matches!(
+            parse_applicability("always"),
+            Ok(Applicability::Always)
+        ));
+        assert!(matches!(
+            parse_applicability("maybe-incorrect"),
+            Ok(Applicability::MaybeIncorrect)
+        ));
+    }
+
+    #[test]
+    fn parse_applicability_rejects_unknown_values_with_a_clear_error() {
+        let error = parse_applicability("sometimes").unwrap_err();
+        assert!(error.to_string().contains("invalid applicability \"sometimes\""));
+    }
+
+    #[test]
+    fn resolve_category_suggests_the_closest_known_category() {
+        let error = resolve_category("pluign").unwrap_err();
+        assert!(error.to_string().contains("did you mean \"plugin\"?"));
+    }
+
+    #[test]
+    fn resolve_category_omits_the_suggestion_when_nothing_is_close_enough() {
+        let error = resolve_category("xyz").unwrap_err();
+        assert!(!error.to_string().contains("did you mean"));
+    }
+
+    #[test]
+    fn annotate_unknown_builtin_suggests_the_closest_known_name() {
+        let error = GritPatternError::new("register_diagnotsic is not defined");
+        let annotated = annotate_unknown_builtin(error);
+        assert!(
+            annotated
+                .to_string()
+                .contains("did you mean \"register_diagnostic\"?")
+        );
+    }
+
+    #[test]
+    fn annotate_unknown_builtin_leaves_unrelated_errors_untouched() {
+        let error = GritPatternError::new("unexpected token");
+        let annotated = annotate_unknown_builtin(error);
+        assert_eq!(annotated.to_string(), "unexpected token");
+    }
+
+    #[test]
+    fn parse_severity_accepts_known_values() {
+        assert!(matches!(parse_severity("error"), Ok(Severity::Error)));
+        assert!(matches!(parse_severity("warning"), Ok(Severity::Warning)));
+        assert!(matches!(
+            parse_severity("information"),
+            Ok(Severity::Information)
+        ));
+        assert!(matches!(parse_severity("hint"), Ok(Severity::Hint)));
+    }
+
+    #[test]
+    fn parse_severity_rejects_unknown_values_with_a_clear_error() {
+        let error = parse_severity("critical").unwrap_err();
+        assert!(error.to_string().contains("invalid severity \"critical\""));
+    }
+
+    /// Compiles `source` as a JS-targeted plugin pattern and runs it against
+    /// `input`, so `register_diagnostic()` itself gets exercised through an
+    /// actual match rather than through its pure helpers in isolation.
+    fn run_against_js(source: &str, input: &str) -> Result<Vec<RuleDiagnostic>, GritPatternError> {
+        let query = compile_for_language(
+            source,
+            Path::new("plugin.grit"),
+            GritTargetLanguage::JsTargetLanguage(JsTargetLanguage),
+        )
+        .expect("test pattern should compile");
+
+        let parse = biome_js_parser::parse(
+            input,
+            biome_js_syntax::JsFileSource::tsx(),
+            biome_js_parser::JsParserOptions::default(),
+        );
+        let file = GritTargetFile {
+            parse: parse.into(),
+            path: PathBuf::from("input.tsx"),
+        };
+
+        query.execute(file).map(|result| result.diagnostics)
+    }
+
+    #[test]
+    fn register_diagnostic_supports_the_two_arg_form_without_a_fixer() {
+        let diagnostics = run_against_js(
+            r#"`console.log($message)` where {
+                register_diagnostic(span=$message, message="avoid console.log")
+            }"#,
+            "console.log('hi');",
+        )
+        .expect("a 2-arg register_diagnostic call should not require a fixer");
+
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn register_diagnostic_rejects_an_unknown_applicability_at_match_time() {
+        let error = run_against_js(
+            r#"`console.log($message)` where {
+                register_diagnostic(
+                    span=$message,
+                    message="avoid console.log",
+                    fixer_description="remove this call",
+                    category="plugin",
+                    applicability="sometimes"
+                )
+            }"#,
+            "console.log('hi');",
+        )
+        .expect_err("an unknown applicability string should be rejected when the rule matches");
+
+        assert!(error.to_string().contains("invalid applicability \"sometimes\""));
+    }
+
+    /// A fully-supplied `register_diagnostic()` call, with `labels` left as
+    /// a placeholder so each test below can substitute a differently
+    /// malformed value for it.
+    fn register_diagnostic_with_labels(labels: &str) -> String {
+        format!(
+            r#"`console.log($message)` where {{
+                register_diagnostic(
+                    span=$message,
+                    message="avoid console.log",
+                    fixer_description="remove this call",
+                    category="plugin",
+                    applicability="always",
+                    labels={labels}
+                )
+            }}"#
+        )
+    }
+
+    #[test]
+    fn parse_secondary_labels_rejects_a_non_list_value() {
+        let error = run_against_js(
+            &register_diagnostic_with_labels(r#""not a list""#),
+            "console.log('hi');",
+        )
+        .expect_err("a non-list labels argument should be rejected");
+
+        assert!(error
+            .to_string()
+            .contains("labels must be a list of { span, label } pairs"));
+    }
+
+    #[test]
+    fn parse_secondary_labels_rejects_a_non_map_item() {
+        let error = run_against_js(
+            &register_diagnostic_with_labels(r#"["not a pair"]"#),
+            "console.log('hi');",
+        )
+        .expect_err("a non-map label item should be rejected");
+
+        assert!(error
+            .to_string()
+            .contains("each label must be a { span, label } pair"));
+    }
+
+    #[test]
+    fn parse_secondary_labels_rejects_a_missing_span() {
+        let error = run_against_js(
+            &register_diagnostic_with_labels(r#"[{ label: "oops" }]"#),
+            "console.log('hi');",
+        )
+        .expect_err("a label missing its span should be rejected");
+
+        assert!(error.to_string().contains("label is missing its span"));
+    }
+
+    #[test]
+    fn parse_secondary_labels_rejects_missing_label_text() {
+        let error = run_against_js(
+            &register_diagnostic_with_labels(r#"[{ span: $message }]"#),
+            "console.log('hi');",
+        )
+        .expect_err("a label missing its text should be rejected");
+
+        assert!(error.to_string().contains("label is missing its text"));
+    }
 }